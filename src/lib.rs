@@ -4,7 +4,9 @@
 
 #![feature(shared)]
 
+use std::cmp;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ptr::Shared;
 
 /// A linked list for wrapping a C linked list.
@@ -19,6 +21,28 @@ use std::ptr::Shared;
 pub struct CLinkedList<T, P, F: Fn(&T) -> P> {
     element: Shared<T>,
     next: F,
+    checked: bool,
+    prev: Option<Box<Fn(&T) -> P>>,
+    sentinel: Option<*mut T>,
+}
+
+/// The error returned by [`try_len`] when a cycle is detected while
+/// traversing a `CLinkedList`.
+///
+/// [`try_len`]: struct.CLinkedList.html#method.try_len
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleError;
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a cycle was detected while traversing the list")
+    }
+}
+
+impl std::error::Error for CycleError {
+    fn description(&self) -> &str {
+        "a cycle was detected while traversing the list"
+    }
 }
 
 /// An iterator over the elements of a `CLinkedList`.
@@ -31,6 +55,12 @@ pub struct CLinkedList<T, P, F: Fn(&T) -> P> {
 pub struct Iter<'a, T: 'a, P: 'a, F: Fn(&T) -> P + 'a> {
     list: &'a CLinkedList<T, P, F>,
     prev: Option<&'a T>,
+    checked: bool,
+    hare: Option<&'a T>,
+    hare_exhausted: bool,
+    cycle_detected: bool,
+    back: Option<&'a T>,
+    done: bool,
 }
 
 impl<'a, T: 'a, F> fmt::Debug for Iter<'a, T, *const T, F>
@@ -63,6 +93,12 @@ where
 pub struct IterMut<'a, T: 'a, P: 'a, F: Fn(&T) -> P + 'a> {
     list: &'a CLinkedList<T, P, F>,
     prev: Option<&'a mut T>,
+    checked: bool,
+    hare: Option<&'a T>,
+    hare_exhausted: bool,
+    cycle_detected: bool,
+    back: Option<&'a mut T>,
+    done: bool,
 }
 
 impl<'a, T: 'a, F> fmt::Debug for IterMut<'a, T, *mut T, F>
@@ -75,6 +111,135 @@ where
     }
 }
 
+/// A cursor over a mutable `CLinkedList` that allows the underlying C list
+/// to be edited in place.
+///
+/// This `struct` is created by the [`cursor_front_mut`] method on
+/// [`CLinkedList`]. See its documentation for more.
+///
+/// Because the crate only has a read-only `next` accessor, editing methods
+/// on the cursor take a `set_next` closure supplied at creation time, which
+/// writes a node's `next` link back into the underlying C list.
+///
+/// [`cursor_front_mut`]: struct.CLinkedList.html#method.cursor_front_mut
+/// [`CLinkedList`]: struct.CLinkedList.html
+pub struct CursorMut<'a, T: 'a, F: Fn(&T) -> *mut T + 'a, S> {
+    list: &'a mut CLinkedList<T, *mut T, F>,
+    current: Option<&'a mut T>,
+    predecessor: Option<*mut T>,
+    set_next: S,
+}
+
+impl<'a, T: 'a, F, S> CursorMut<'a, T, F, S>
+where
+    F: Fn(&T) -> *mut T,
+    S: FnMut(&mut T, *mut T),
+{
+    /// Provides a mutable reference to the element the cursor is currently
+    /// positioned over, or `None` if the cursor has moved past the back of
+    /// the list.
+    pub fn current(&mut self) -> Option<&mut T> {
+        match self.current {
+            Some(ref mut cur) => Some(*cur),
+            None => None,
+        }
+    }
+
+    /// Moves the cursor to the next element of the list.
+    ///
+    /// On a [sentinel-anchored] ring, the cursor stops at the sentinel the
+    /// same way it stops at NULL on a NULL-terminated list, rather than
+    /// treating the sentinel as a data element.
+    ///
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
+    pub fn move_next(&mut self) {
+        let current_ptr = match self.current.take() {
+            Some(cur) => cur as *mut T,
+            None => return,
+        };
+        let next_ptr = (self.list.next)(unsafe { &*current_ptr });
+        self.predecessor = Some(current_ptr);
+        self.current = if self.list.is_terminal(next_ptr) {
+            None
+        } else {
+            Some(unsafe { &mut *next_ptr })
+        };
+    }
+
+    /// Unlinks the current element from the list by rewriting the
+    /// predecessor's `next` link to skip over it (for the front element of a
+    /// [sentinel-anchored] ring, the sentinel's), and returns the
+    /// now-unlinked raw pointer so the caller retains ownership for
+    /// freeing it.
+    ///
+    /// The cursor advances to the element that followed the removed one, or
+    /// stops (the same as reaching NULL) on reaching a [sentinel-anchored]
+    /// ring's sentinel. Removing the only remaining element of a
+    /// NULL-terminated list leaves the list's head pointer dangling; the
+    /// list must not be used afterwards in that case.
+    ///
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
+    pub fn remove_current(&mut self) -> Option<*mut T> {
+        let current_ptr = match self.current.take() {
+            Some(cur) => cur as *mut T,
+            None => return None,
+        };
+        let next_ptr = (self.list.next)(unsafe { &*current_ptr });
+        match self.predecessor {
+            Some(pred_ptr) => (self.set_next)(unsafe { &mut *pred_ptr }, next_ptr),
+            None => {
+                if let Some(sentinel) = self.list.sentinel {
+                    (self.set_next)(unsafe { &mut *sentinel }, next_ptr);
+                }
+                if let Some(p) = Shared::new(next_ptr) {
+                    self.list.element = p;
+                }
+            }
+        }
+        self.current = if self.list.is_terminal(next_ptr) {
+            None
+        } else {
+            Some(unsafe { &mut *next_ptr })
+        };
+        Some(current_ptr)
+    }
+
+    /// Splices `other_head`, and the chain following it, into the list
+    /// immediately after the current element.
+    ///
+    /// The tail of `other_head`'s chain is found by walking `next`, and is
+    /// rejoined to whatever followed the current element before the splice.
+    /// The cursor never sits on a [sentinel-anchored] ring's sentinel (see
+    /// [`move_next`] and [`remove_current`]), so whenever it has a current
+    /// element that element is a real one. If the cursor has moved past the
+    /// back of the list, or `other_head` is NULL, nothing is spliced.
+    ///
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
+    /// [`move_next`]: #method.move_next
+    /// [`remove_current`]: #method.remove_current
+    pub fn splice_after(&mut self, other_head: *mut T) {
+        let current_ptr = match self.current {
+            Some(ref mut cur) => (*cur) as *mut T,
+            None => return,
+        };
+        if self.list.is_terminal(other_head) {
+            return;
+        }
+
+        let old_next = (self.list.next)(unsafe { &*current_ptr });
+        let mut tail = other_head;
+        loop {
+            let p = (self.list.next)(unsafe { &*tail });
+            if p.is_null() {
+                break;
+            }
+            tail = p;
+        }
+        (self.set_next)(unsafe { &mut *tail }, old_next);
+        (self.set_next)(unsafe { &mut *current_ptr }, other_head);
+    }
+}
+
 impl<T, F> CLinkedList<T, *const T, F>
 where
     F: Fn(&T) -> *const T,
@@ -88,11 +253,43 @@ where
             Self {
                 element: p,
                 next: next,
+                checked: false,
+                prev: None,
+                sentinel: None,
             }
         })
     }
 
+    /// Creates a `CLinkedList` by wrapping a C linked list, the same as
+    /// [`from_const_ptr`], but guards its iterators against cycles.
+    ///
+    /// The returned list uses Floyd's tortoise-and-hare algorithm while
+    /// iterating, so [`contains`] and its iterators can never loop forever,
+    /// even if the underlying C list turns out to be circular or malformed.
+    /// This comes at the cost of walking the list roughly twice as many
+    /// times as the zero-overhead constructors produced by
+    /// [`from_const_ptr`]. [`len`] and [`back`] are not guarded and still
+    /// loop forever on a circular list; [`try_len`] is the cycle-safe
+    /// length.
+    ///
+    /// [`from_const_ptr`]: struct.CLinkedList.html#method.from_const_ptr
+    /// [`len`]: struct.CLinkedList.html#method.len
+    /// [`back`]: struct.CLinkedList.html#method.back
+    /// [`try_len`]: struct.CLinkedList.html#method.try_len
+    /// [`contains`]: struct.CLinkedList.html#method.contains
+    pub fn from_const_ptr_checked(head: *const T, next: F) -> Option<Self> {
+        Self::from_const_ptr(head, next).map(|mut list| {
+            list.checked = true;
+            list
+        })
+    }
+
     /// Returns the length of the `CLinkedList`.
+    ///
+    /// This loops forever if the underlying C list is circular; use
+    /// [`try_len`] if that cannot be ruled out.
+    ///
+    /// [`try_len`]: struct.CLinkedList.html#method.try_len
     pub fn len(&self) -> usize {
         let mut e = self.element;
         let mut ret = 1;
@@ -103,6 +300,57 @@ where
         ret
     }
 
+    /// Returns the length of the `CLinkedList`, or a [`CycleError`] if a
+    /// cycle is detected while walking it.
+    ///
+    /// Unlike [`len`], this is safe to call even when the underlying C list
+    /// might be circular: it advances a slow pointer one step and a fast
+    /// pointer two steps per iteration (Floyd's tortoise-and-hare
+    /// algorithm) and reports a cycle as soon as the two meet.
+    ///
+    /// [`len`]: struct.CLinkedList.html#method.len
+    /// [`CycleError`]: struct.CycleError.html
+    pub fn try_len(&self) -> Result<usize, CycleError> {
+        let mut slow = self.element;
+        let mut fast = self.element;
+        let mut ret = 1;
+        loop {
+            let p = (self.next)(unsafe { fast.as_ref() }) as *mut T;
+            if p.is_null() {
+                return Ok(ret);
+            }
+            fast = Shared::new(p).unwrap();
+            ret += 1;
+
+            let p = (self.next)(unsafe { fast.as_ref() }) as *mut T;
+            if p.is_null() {
+                return Ok(ret);
+            }
+            fast = Shared::new(p).unwrap();
+            ret += 1;
+
+            slow = Shared::new((self.next)(unsafe { slow.as_ref() }) as *mut T).unwrap();
+
+            if fast.as_ptr() == slow.as_ptr() {
+                return Err(CycleError);
+            }
+        }
+    }
+
+    /// Provides a reference to the back element, or `None` if the list is
+    /// empty. This is found by walking `next` until the last element is
+    /// reached, so it runs in `O(n)`.
+    pub fn back(&self) -> Option<&T> {
+        if self.element.as_ptr().is_null() {
+            return None;
+        }
+        let mut e = self.element;
+        while let Some(p) = Shared::new((self.next)(unsafe { e.as_ref() }) as *mut T) {
+            e = p;
+        }
+        Some(unsafe { e.as_ref() })
+    }
+
     /// Returns `true` if the `CLinkedList` contains an element equal to the
     /// given value.
     pub fn contains(&self, x: &T) -> bool
@@ -126,29 +374,200 @@ where
             Self {
                 element: p,
                 next: next,
+                checked: false,
+                prev: None,
+                sentinel: None,
+            }
+        })
+    }
+
+    /// Creates a `CLinkedList` by wrapping a doubly-linked C list. `head`
+    /// points to the head element of the list or is NULL for a list of
+    /// length 0. `next` and `prev` are functions that take an element and
+    /// return a mutable raw pointer to the next and previous element,
+    /// respectively.
+    ///
+    /// The stored `prev` function is what `next_back` on [`iter`]/[`iter_mut`]
+    /// follows, so the list can be walked tail-to-head (for example via
+    /// [`rev`]) the same way std's `LinkedList` can; on a list built by any
+    /// other constructor `next_back` returns `None`. The `back`/`back_mut`
+    /// accessors do not use `prev` and work on every list.
+    ///
+    /// [`iter`]: struct.CLinkedList.html#method.iter
+    /// [`iter_mut`]: struct.CLinkedList.html#method.iter_mut
+    /// [`rev`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.rev
+    pub fn from_mut_ptr_doubly<G>(head: *mut T, next: F, prev: G) -> Option<Self>
+    where
+        G: Fn(&T) -> *mut T + 'static,
+    {
+        Shared::new(head).map(|p| {
+            Self {
+                element: p,
+                next: next,
+                checked: false,
+                prev: Some(Box::new(prev)),
+                sentinel: None,
             }
         })
     }
 
+    /// Creates a `CLinkedList` by wrapping a circular C list anchored by a
+    /// sentinel node, the same convention as the Linux kernel's
+    /// `list_head`: `sentinel` is a real, allocated node that is not itself
+    /// a data element, and the ring is terminated by returning to its
+    /// address rather than by a NULL `next`.
+    ///
+    /// `next(sentinel)` is the first data element, or `sentinel` itself for
+    /// an empty ring. [`len`], [`is_empty`], [`contains`] and the iterators
+    /// all exclude the sentinel and stop as soon as a `next` step lands back
+    /// on its address.
+    ///
+    /// [`len`]: struct.CLinkedList.html#method.len
+    /// [`is_empty`]: struct.CLinkedList.html#method.is_empty
+    /// [`contains`]: struct.CLinkedList.html#method.contains
+    pub fn from_sentinel_ptr(sentinel: *mut T, next: F) -> Option<Self> {
+        if sentinel.is_null() {
+            return None;
+        }
+        let head = next(unsafe { &*sentinel });
+        Shared::new(head).map(|p| Self {
+            element: p,
+            next: next,
+            checked: false,
+            prev: None,
+            sentinel: Some(sentinel),
+        })
+    }
+
+    /// Creates a `CLinkedList` by wrapping a C linked list, the same as
+    /// [`from_mut_ptr`], but guards its iterators against cycles.
+    ///
+    /// The returned list uses Floyd's tortoise-and-hare algorithm while
+    /// iterating, so [`contains`] and its iterators can never loop forever,
+    /// even if the underlying C list turns out to be circular or malformed.
+    /// This comes at the cost of walking the list roughly twice as many
+    /// times as the zero-overhead constructors produced by
+    /// [`from_mut_ptr`]. [`len`], [`back`] and [`back_mut`] are not guarded
+    /// and still loop forever on a circular list; [`try_len`] is the
+    /// cycle-safe length.
+    ///
+    /// [`from_mut_ptr`]: struct.CLinkedList.html#method.from_mut_ptr
+    /// [`len`]: struct.CLinkedList.html#method.len
+    /// [`back`]: struct.CLinkedList.html#method.back
+    /// [`back_mut`]: struct.CLinkedList.html#method.back_mut
+    /// [`try_len`]: struct.CLinkedList.html#method.try_len
+    /// [`contains`]: struct.CLinkedList.html#method.contains
+    pub fn from_mut_ptr_checked(head: *mut T, next: F) -> Option<Self> {
+        Self::from_mut_ptr(head, next).map(|mut list| {
+            list.checked = true;
+            list
+        })
+    }
+
     /// Provides a forward iterator with mutable references.
     pub fn iter_mut(&mut self) -> IterMut<T, *mut T, F> {
         IterMut {
             list: self,
             prev: None,
+            checked: self.checked,
+            hare: None,
+            hare_exhausted: false,
+            cycle_detected: false,
+            back: None,
+            done: false,
         }
     }
 
+    /// Returns `true` if `p` marks the end of the list: a NULL pointer, or
+    /// (for a [sentinel-anchored] ring) the sentinel's address.
+    ///
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
+    fn is_terminal(&self, p: *mut T) -> bool {
+        p.is_null() || self.sentinel == Some(p)
+    }
+
     /// Returns the length of the `CLinkedList`.
+    ///
+    /// This loops forever if the underlying C list is circular (and not
+    /// [sentinel-anchored]); use [`try_len`] if that cannot be ruled out.
+    ///
+    /// [`try_len`]: struct.CLinkedList.html#method.try_len
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
     pub fn len(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
         let mut e = self.element;
         let mut ret = 1;
-        while let Some(p) = Shared::new((self.next)(unsafe { e.as_ref() })) {
-            e = p;
+        loop {
+            let p = (self.next)(unsafe { e.as_ref() });
+            if self.is_terminal(p) {
+                break;
+            }
+            e = Shared::new(p).unwrap();
             ret += 1;
         }
         ret
     }
 
+    /// Returns the length of the `CLinkedList`, or a [`CycleError`] if a
+    /// cycle is detected while walking it.
+    ///
+    /// Unlike [`len`], this is safe to call even when the underlying C list
+    /// might be circular: it advances a slow pointer one step and a fast
+    /// pointer two steps per iteration (Floyd's tortoise-and-hare
+    /// algorithm) and reports a cycle as soon as the two meet.
+    ///
+    /// [`len`]: struct.CLinkedList.html#method.len
+    /// [`CycleError`]: struct.CycleError.html
+    pub fn try_len(&self) -> Result<usize, CycleError> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        let mut slow = self.element;
+        let mut fast = self.element;
+        let mut ret = 1;
+        loop {
+            let p = (self.next)(unsafe { fast.as_ref() });
+            if self.is_terminal(p) {
+                return Ok(ret);
+            }
+            fast = Shared::new(p).unwrap();
+            ret += 1;
+
+            let p = (self.next)(unsafe { fast.as_ref() });
+            if self.is_terminal(p) {
+                return Ok(ret);
+            }
+            fast = Shared::new(p).unwrap();
+            ret += 1;
+
+            slow = Shared::new((self.next)(unsafe { slow.as_ref() })).unwrap();
+
+            if fast.as_ptr() == slow.as_ptr() {
+                return Err(CycleError);
+            }
+        }
+    }
+
+    /// Provides a reference to the back element, or `None` if the list is
+    /// empty. This is found by walking `next` until the last element is
+    /// reached, so it runs in `O(n)`.
+    pub fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut e = self.element;
+        loop {
+            let p = (self.next)(unsafe { e.as_ref() });
+            if self.is_terminal(p) {
+                break;
+            }
+            e = Shared::new(p).unwrap();
+        }
+        Some(unsafe { e.as_ref() })
+    }
+
     /// Returns `true` if the `CLinkedList` contains an element equal to the
     /// given value.
     pub fn contains(&self, x: &T) -> bool
@@ -161,10 +580,51 @@ where
     /// Provides a mutable reference to the front element, or `None` if the list
     /// is empty.
     pub fn front_mut(&mut self) -> Option<&mut T> {
-        if self.element.as_ptr().is_null() {
+        if self.is_empty() {
+            None
+        } else {
+            Some(unsafe { self.element.as_mut() })
+        }
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// list is empty. This is found by walking `next` until the last
+    /// element is reached, so it runs in `O(n)`.
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut e = self.element;
+        loop {
+            let p = (self.next)(unsafe { e.as_ref() });
+            if self.is_terminal(p) {
+                break;
+            }
+            e = Shared::new(p).unwrap();
+        }
+        Some(unsafe { e.as_mut() })
+    }
+
+    /// Returns a cursor positioned at the front element, for in-place
+    /// removal and splicing of nodes in the underlying C list.
+    ///
+    /// See [`CursorMut`] for why a `set_next` closure is required.
+    ///
+    /// [`CursorMut`]: struct.CursorMut.html
+    pub fn cursor_front_mut<S>(&mut self, set_next: S) -> CursorMut<T, F, S>
+    where
+        S: FnMut(&mut T, *mut T),
+    {
+        let current = if self.is_empty() {
             None
         } else {
             Some(unsafe { self.element.as_mut() })
+        };
+        CursorMut {
+            list: self,
+            current: current,
+            predecessor: None,
+            set_next: set_next,
         }
     }
 }
@@ -178,18 +638,32 @@ where
         Iter {
             list: self,
             prev: None,
+            checked: self.checked,
+            hare: None,
+            hare_exhausted: false,
+            cycle_detected: false,
+            back: None,
+            done: false,
         }
     }
 
     /// Returns `true` if the `CLinkedList` is empty.
+    ///
+    /// For a [sentinel-anchored] ring, this means the sentinel's `next`
+    /// points back at itself, rather than the head pointer being NULL.
+    ///
+    /// [sentinel-anchored]: struct.CLinkedList.html#method.from_sentinel_ptr
     pub fn is_empty(&self) -> bool {
-        self.element.as_ptr().is_null()
+        match self.sentinel {
+            Some(sentinel) => self.element.as_ptr() == sentinel,
+            None => self.element.as_ptr().is_null(),
+        }
     }
 
     /// Provides a reference to the front element, or `None` if the list is
     /// empty.
     pub fn front(&self) -> Option<&T> {
-        if self.element.as_ptr().is_null() {
+        if self.is_empty() {
             None
         } else {
             Some(unsafe { self.element.as_ref() })
@@ -205,24 +679,61 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.prev
+        if self.checked && self.cycle_detected {
+            return None;
+        }
+
+        let p_element = self.prev
             .map_or_else(
                 || Some(self.list.element.as_ptr()),
                 |prev| Some((self.list.next)(prev) as *mut T),
             )
-            .and_then(|p_element| {
-                if p_element.is_null() {
-                    None
-                } else {
-                    self.prev = unsafe { p_element.as_ref() };
-                    self.prev
+            .and_then(|p_element| if p_element.is_null() { None } else { Some(p_element) });
+
+        let p_element = match p_element {
+            Some(p) => p,
+            None => return None,
+        };
+
+        // The hare is compared against `p_element` *before* taking this
+        // call's two hops, so that by the time the tortoise has taken `n`
+        // steps the hare has taken exactly `2n`, matching `try_len`.
+        // Comparing only after also advancing the hare this call would
+        // compare the tortoise at `n` against the hare at `2n + 2` instead.
+        if self.checked && !self.hare_exhausted {
+            match self.hare {
+                None => self.hare = unsafe { self.list.element.as_ptr().as_ref() },
+                Some(hare) => {
+                    if hare as *const T == p_element as *const T {
+                        self.cycle_detected = true;
+                        return None;
+                    }
                 }
-            })
+            }
+            for _ in 0..2 {
+                let p_hare = (self.list.next)(self.hare.unwrap()) as *mut T;
+                if p_hare.is_null() {
+                    self.hare_exhausted = true;
+                    break;
+                }
+                self.hare = unsafe { p_hare.as_ref() };
+            }
+        }
+
+        self.prev = unsafe { p_element.as_ref() };
+        self.prev
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.list.len();
-        (len, Some(len))
+        if self.checked {
+            match self.list.try_len() {
+                Ok(len) => (len, Some(len)),
+                Err(_) => (0, None),
+            }
+        } else {
+            let len = self.list.len();
+            (len, Some(len))
+        }
     }
 }
 
@@ -236,54 +747,214 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.prev
+        if self.done || (self.checked && self.cycle_detected) {
+            return None;
+        }
+
+        let p_element = self.prev
             .map_or_else(
                 || Some(self.list.element.as_ptr()),
                 |prev| Some((self.list.next)(prev)),
             )
-            .and_then(|p_element| {
-                if p_element.is_null() {
-                    None
-                } else {
-                    self.prev = unsafe { p_element.as_ref() };
-                    self.prev
+            .and_then(|p_element| if self.list.is_terminal(p_element) { None } else { Some(p_element) });
+
+        let p_element = match p_element {
+            Some(p) => p,
+            None => return None,
+        };
+
+        if let Some(back) = self.back {
+            if back as *const T == p_element as *const T {
+                self.done = true;
+                return None;
+            }
+        }
+
+        // See the `*const T` impl of `next` for why the hare is compared
+        // before being advanced this call rather than after.
+        if self.checked && !self.hare_exhausted {
+            match self.hare {
+                None => self.hare = unsafe { self.list.element.as_ptr().as_ref() },
+                Some(hare) => {
+                    if hare as *const T == p_element as *const T {
+                        self.cycle_detected = true;
+                        return None;
+                    }
                 }
-            })
+            }
+            for _ in 0..2 {
+                let p_hare = (self.list.next)(self.hare.unwrap());
+                if self.list.is_terminal(p_hare) {
+                    self.hare_exhausted = true;
+                    break;
+                }
+                self.hare = unsafe { p_hare.as_ref() };
+            }
+        }
+
+        self.prev = unsafe { p_element.as_ref() };
+        self.prev
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.list.len();
-        (len, Some(len))
+        if self.checked {
+            match self.list.try_len() {
+                Ok(len) => (len, Some(len)),
+                Err(_) => (0, None),
+            }
+        } else {
+            let len = self.list.len();
+            (len, Some(len))
+        }
     }
 }
 
 impl<'a, T, F: Fn(&T) -> *mut T> ExactSizeIterator for Iter<'a, T, *mut T, F> {}
 
+impl<'a, T: 'a, F> DoubleEndedIterator for Iter<'a, T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let prev_fn = match self.list.prev {
+            Some(ref prev_fn) => prev_fn,
+            None => return None,
+        };
+
+        let p_back = match self.back {
+            None => {
+                let mut e = self.list.element;
+                while let Some(p) = Shared::new((self.list.next)(unsafe { e.as_ref() })) {
+                    e = p;
+                }
+                e.as_ptr()
+            }
+            Some(back) => prev_fn(back),
+        };
+        if p_back.is_null() {
+            self.done = true;
+            return None;
+        }
+
+        if let Some(prev) = self.prev {
+            if prev as *const T == p_back as *const T {
+                self.done = true;
+                return None;
+            }
+        }
+
+        self.back = unsafe { p_back.as_ref() };
+        self.back
+    }
+}
+
 impl<'a, T: 'a, F: Fn(&T) -> *mut T> Iterator for IterMut<'a, T, *mut T, F> {
     type Item = &'a mut T;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.done || (self.checked && self.cycle_detected) {
+            return None;
+        }
+
         let p_element = match self.prev {
             None => self.list.element.as_ptr(),
             Some(ref prev) => (self.list.next)(*prev),
         };
-        if p_element.is_null() {
-            None
-        } else {
-            self.prev = unsafe { p_element.as_mut() };
-            unsafe { p_element.as_mut() }
+        if self.list.is_terminal(p_element) {
+            return None;
+        }
+
+        if let Some(ref back) = self.back {
+            if &**back as *const T == p_element as *const T {
+                self.done = true;
+                return None;
+            }
         }
+
+        // See the `*const T` impl of `Iter::next` for why the hare is
+        // compared before being advanced this call rather than after.
+        if self.checked && !self.hare_exhausted {
+            match self.hare {
+                None => self.hare = unsafe { self.list.element.as_ptr().as_ref() },
+                Some(hare) => {
+                    if hare as *const T == p_element as *const T {
+                        self.cycle_detected = true;
+                        return None;
+                    }
+                }
+            }
+            for _ in 0..2 {
+                let p_hare = (self.list.next)(self.hare.unwrap());
+                if self.list.is_terminal(p_hare) {
+                    self.hare_exhausted = true;
+                    break;
+                }
+                self.hare = unsafe { p_hare.as_ref() };
+            }
+        }
+
+        self.prev = unsafe { p_element.as_mut() };
+        unsafe { p_element.as_mut() }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.list.len();
-        (len, Some(len))
+        if self.checked {
+            match self.list.try_len() {
+                Ok(len) => (len, Some(len)),
+                Err(_) => (0, None),
+            }
+        } else {
+            let len = self.list.len();
+            (len, Some(len))
+        }
     }
 }
 
 impl<'a, T, F: Fn(&T) -> *mut T> ExactSizeIterator for IterMut<'a, T, *mut T, F> {}
 
+impl<'a, T: 'a, F: Fn(&T) -> *mut T> DoubleEndedIterator for IterMut<'a, T, *mut T, F> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let prev_fn = match self.list.prev {
+            Some(ref prev_fn) => prev_fn,
+            None => return None,
+        };
+
+        let p_back = match self.back {
+            None => {
+                let mut e = self.list.element;
+                while let Some(p) = Shared::new((self.list.next)(unsafe { e.as_ref() })) {
+                    e = p;
+                }
+                e.as_ptr()
+            }
+            Some(ref back) => prev_fn(&**back),
+        };
+        if p_back.is_null() {
+            self.done = true;
+            return None;
+        }
+
+        if let Some(ref prev) = self.prev {
+            if &**prev as *const T == p_back as *const T {
+                self.done = true;
+                return None;
+            }
+        }
+
+        self.back = unsafe { p_back.as_mut() };
+        unsafe { p_back.as_mut() }
+    }
+}
+
 impl<'a, T: 'a, F> IntoIterator for &'a CLinkedList<T, *const T, F>
 where
     F: Fn(&T) -> *const T,
@@ -338,6 +1009,113 @@ where
     }
 }
 
+/// Two `CLinkedList`s are only comparable when they share the same `F`, so
+/// lists built from distinct closure literals (even textually identical
+/// ones, since each closure expression is its own anonymous type) cannot be
+/// compared against each other; only lists sharing one named `fn` or a
+/// single stored closure value can.
+impl<T: PartialEq, F> PartialEq for CLinkedList<T, *const T, F>
+where
+    F: Fn(&T) -> *const T,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, F> Eq for CLinkedList<T, *const T, F>
+where
+    F: Fn(&T) -> *const T,
+{
+}
+
+impl<T: PartialOrd, F> PartialOrd for CLinkedList<T, *const T, F>
+where
+    F: Fn(&T) -> *const T,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord, F> Ord for CLinkedList<T, *const T, F>
+where
+    F: Fn(&T) -> *const T,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// As with the `PartialEq` impl above, only lists sharing the same `F` are
+/// comparable, so only those can be expected to hash consistently with each
+/// other.
+impl<T: Hash, F> Hash for CLinkedList<T, *const T, F>
+where
+    F: Fn(&T) -> *const T,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // `try_len` rather than `len` so hashing a checked list keeps the
+        // no-hang guarantee even if its backing C list turns out circular.
+        self.try_len().unwrap_or(0).hash(state);
+        for elt in self {
+            elt.hash(state);
+        }
+    }
+}
+
+/// See the `*const T` impl above: only lists sharing the same `F` are
+/// comparable.
+impl<T: PartialEq, F> PartialEq for CLinkedList<T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, F> Eq for CLinkedList<T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+}
+
+impl<T: PartialOrd, F> PartialOrd for CLinkedList<T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord, F> Ord for CLinkedList<T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// See the `*const T` impl above: only lists sharing the same `F` are
+/// comparable, so only those can be expected to hash consistently with each
+/// other.
+impl<T: Hash, F> Hash for CLinkedList<T, *mut T, F>
+where
+    F: Fn(&T) -> *mut T,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // `try_len` rather than `len` so hashing a checked list keeps the
+        // no-hang guarantee even if its backing C list turns out circular.
+        self.try_len().unwrap_or(0).hash(state);
+        for elt in self {
+            elt.hash(state);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -444,4 +1222,280 @@ mod tests {
         list.front_mut().unwrap().val = 10;
         assert_eq!(list.front().unwrap().val, 10);
     }
+
+    #[test]
+    fn test_checked_list_survives_a_cycle() {
+        let ptr = make_list_mut();
+        // Link the tail back to the head, turning the list into a ring.
+        unsafe {
+            let mut tail = ptr;
+            while !(*tail).next.is_null() {
+                tail = (*tail).next;
+            }
+            (*tail).next = ptr;
+        }
+
+        let list = CLinkedList::from_mut_ptr_checked(ptr, |n| n.next).unwrap();
+        assert_eq!(list.try_len(), Err(CycleError));
+
+        // The checked iterator must terminate instead of looping forever.
+        // Floyd's algorithm is deterministic for this fixture: the hare has
+        // lapped the ring and meets the tortoise right after it yields the
+        // third (and last distinct) element, so the iterator stops after
+        // yielding exactly one full lap, [1, 2, 3].
+        let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[1, 2, 3]);
+    }
+
+    fn make_linear_list_mut(vals: &[u32]) -> *mut TestNodeMut {
+        fn malloc<T>(t: T) -> *mut T {
+            Box::into_raw(Box::new(t)) as *mut T
+        }
+
+        let mut head = std::ptr::null_mut();
+        for &val in vals.iter().rev() {
+            head = malloc(TestNodeMut { val: val, next: head });
+        }
+        head
+    }
+
+    #[test]
+    fn test_checked_list_yields_every_element_of_an_acyclic_list() {
+        // A checked list is only allowed to differ from its unchecked
+        // counterpart when the underlying C list is actually circular; on a
+        // plain NULL-terminated list it must yield every element, for any
+        // length relative to the hare's 2-steps-per-tortoise-step stride.
+        for len in 1..=5 {
+            let vals = (1..=len).collect::<Vec<u32>>();
+            let ptr = make_linear_list_mut(&vals);
+            let list = CLinkedList::from_mut_ptr_checked(ptr, |n| n.next).unwrap();
+            let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+            assert_eq!(vs, vals, "checked iterator truncated a {}-element acyclic list", len);
+        }
+    }
+
+    #[derive(PartialEq, Eq)]
+    struct TestNodeDoubly {
+        val: u32,
+        next: *mut TestNodeDoubly,
+        prev: *mut TestNodeDoubly,
+    }
+
+    fn make_list_doubly() -> *mut TestNodeDoubly {
+        fn malloc<T>(t: T) -> *mut T {
+            Box::into_raw(Box::new(t)) as *mut T
+        }
+
+        let head = malloc(TestNodeDoubly {
+            val: 1,
+            next: std::ptr::null_mut(),
+            prev: std::ptr::null_mut(),
+        });
+        let middle = malloc(TestNodeDoubly {
+            val: 2,
+            next: std::ptr::null_mut(),
+            prev: head,
+        });
+        let tail = malloc(TestNodeDoubly {
+            val: 3,
+            next: std::ptr::null_mut(),
+            prev: middle,
+        });
+        unsafe {
+            (*head).next = middle;
+            (*middle).next = tail;
+        }
+        head
+    }
+
+    #[test]
+    fn test_using_doubly_linked_ptr() {
+        let ptr = make_list_doubly();
+        let list = CLinkedList::from_mut_ptr_doubly(ptr, |n| n.next, |n| n.prev).unwrap();
+
+        assert_eq!(list.back().unwrap().val, 3);
+        let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[1, 2, 3]);
+        let vs = list.iter().rev().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[3, 2, 1]);
+    }
+
+    struct TestNodeOrd {
+        val: u32,
+        next: *mut TestNodeOrd,
+    }
+
+    impl PartialEq for TestNodeOrd {
+        fn eq(&self, other: &Self) -> bool {
+            self.val == other.val
+        }
+    }
+
+    impl Eq for TestNodeOrd {}
+
+    impl PartialOrd for TestNodeOrd {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            self.val.partial_cmp(&other.val)
+        }
+    }
+
+    impl Ord for TestNodeOrd {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.val.cmp(&other.val)
+        }
+    }
+
+    impl std::hash::Hash for TestNodeOrd {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.val.hash(state);
+        }
+    }
+
+    fn make_list_ord(vals: &[u32]) -> *mut TestNodeOrd {
+        fn malloc<T>(t: T) -> *mut T {
+            Box::into_raw(Box::new(t)) as *mut T
+        }
+
+        let mut head = std::ptr::null_mut();
+        for &val in vals.iter().rev() {
+            head = malloc(TestNodeOrd { val: val, next: head });
+        }
+        head
+    }
+
+    fn next_ord(n: &TestNodeOrd) -> *mut TestNodeOrd {
+        n.next
+    }
+
+    #[test]
+    fn test_eq_ord_and_hash() {
+        // `PartialEq`/`PartialOrd`/`Hash` are only implemented for two
+        // `CLinkedList`s sharing the same `F`, so comparing instances built
+        // from this closure type requires a named `fn` here rather than the
+        // inline closure per call used elsewhere in this file.
+        let a = CLinkedList::from_mut_ptr(make_list_ord(&[1, 2, 3]), next_ord).unwrap();
+        let b = CLinkedList::from_mut_ptr(make_list_ord(&[1, 2, 3]), next_ord).unwrap();
+        let c = CLinkedList::from_mut_ptr(make_list_ord(&[1, 2, 4]), next_ord).unwrap();
+        let d = CLinkedList::from_mut_ptr(make_list_ord(&[1, 2]), next_ord).unwrap();
+
+        assert!(a == b);
+        assert!(a != c);
+        assert!(a < c);
+        assert!(d < a);
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&b));
+        assert!(!set.contains(&c));
+    }
+
+    #[test]
+    fn test_cursor_remove_and_splice() {
+        let ptr = make_list_mut();
+        let mut list = CLinkedList::from_mut_ptr(ptr, |n| n.next).unwrap();
+
+        let mut cursor = list.cursor_front_mut(|n, next| n.next = next);
+        cursor.move_next();
+        let removed = cursor.remove_current().unwrap();
+        let removed_val = unsafe { Box::from_raw(removed) }.val;
+        assert_eq!(removed_val, 2);
+
+        let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[1, 3]);
+
+        let spliced = Box::into_raw(Box::new(TestNodeMut {
+            val: 99,
+            next: std::ptr::null_mut(),
+        }));
+        let mut cursor = list.cursor_front_mut(|n, next| n.next = next);
+        cursor.splice_after(spliced);
+
+        let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[1, 99, 3]);
+    }
+
+    #[test]
+    fn test_cursor_remove_front() {
+        let ptr = make_list_mut();
+        let mut list = CLinkedList::from_mut_ptr(ptr, |n| n.next).unwrap();
+
+        // `predecessor` is `None` here, so this exercises the branch of
+        // `remove_current` that rewrites `self.list.element` directly
+        // rather than going through `set_next`.
+        let mut cursor = list.cursor_front_mut(|n, next| n.next = next);
+        let removed = cursor.remove_current().unwrap();
+        let removed_val = unsafe { Box::from_raw(removed) }.val;
+        assert_eq!(removed_val, 1);
+
+        assert_eq!(list.front().unwrap().val, 2);
+        let vs = list.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[2, 3]);
+    }
+
+    #[test]
+    fn test_cursor_remove_last_remaining_element() {
+        fn malloc<T>(t: T) -> *mut T {
+            Box::into_raw(Box::new(t)) as *mut T
+        }
+
+        let ptr = malloc(TestNodeMut {
+            val: 1,
+            next: std::ptr::null_mut(),
+        });
+        let mut list = CLinkedList::from_mut_ptr(ptr, |n| n.next).unwrap();
+
+        // Also `predecessor == None`, but here `next_ptr` is NULL too, so
+        // `self.list.element` ends up dangling, as documented.
+        let mut cursor = list.cursor_front_mut(|n, next| n.next = next);
+        let removed = cursor.remove_current().unwrap();
+        let removed_val = unsafe { Box::from_raw(removed) }.val;
+        assert_eq!(removed_val, 1);
+        assert!(cursor.current().is_none());
+    }
+
+    #[test]
+    fn test_sentinel_ring() {
+        fn malloc<T>(t: T) -> *mut T {
+            Box::into_raw(Box::new(t)) as *mut T
+        }
+
+        let sentinel = malloc(TestNodeMut {
+            val: 0,
+            next: std::ptr::null_mut(),
+        });
+        unsafe {
+            (*sentinel).next = sentinel;
+        }
+
+        let empty = CLinkedList::from_sentinel_ptr(sentinel, |n| n.next).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.iter().count(), 0);
+
+        let one = malloc(TestNodeMut {
+            val: 1,
+            next: sentinel,
+        });
+        let two = malloc(TestNodeMut {
+            val: 2,
+            next: one,
+        });
+        unsafe {
+            (*sentinel).next = two;
+        }
+
+        let ring = CLinkedList::from_sentinel_ptr(sentinel, |n| n.next).unwrap();
+        assert!(!ring.is_empty());
+        assert_eq!(ring.len(), 2);
+        let vs = ring.iter().map(|n| n.val).collect::<Vec<_>>();
+        assert_eq!(vs, &[2, 1]);
+        assert!(!ring.contains(&TestNodeMut {
+            val: 0,
+            next: std::ptr::null_mut(),
+        }));
+        assert!(ring.contains(&TestNodeMut {
+            val: 1,
+            next: sentinel,
+        }));
+    }
 }